@@ -1,6 +1,6 @@
 // src/lib.rs
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use thiserror::Error;
 
@@ -10,6 +10,18 @@ use embedded_hal::i2c::I2c;
 // linux-embedded-hal에서 제공하는 구체적인 타입과 공개된 에러 타입을 사용합니다.
 use linux_embedded_hal::{Delay, I2cdev, I2CError};
 
+use bitflags::bitflags;
+
+bitflags! {
+    // AHT10/AHT20이 문서화하는 상태 레지스터 레이아웃입니다.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct StatusFlags: u8 {
+        const BUSY = 1 << 7;
+        const MODE = (1 << 6) | (1 << 5);
+        const CRC = 1 << 4;
+        const CALIBRATION_ENABLE = 1 << 3;
+    }
+}
 
 // --- C와 Python이 이해할 수 있는 결과 구조체 (외부 공개) ---
 #[repr(C)]
@@ -17,6 +29,11 @@ use linux_embedded_hal::{Delay, I2cdev, I2CError};
 pub struct SensorReading {
     pub temperature: f64,
     pub humidity: f64,
+    /// Magnus 공식으로 계산한 이슬점(°C).
+    pub dew_point: f64,
+    /// 습도지수(Humidex, °C). HVAC/온실 모니터링 등 결로/쾌적도 판단에 사용합니다.
+    /// NWS/Steadman "heat index"와는 다른 캐나다식 체감 지표이므로 혼동하지 마세요.
+    pub humidex: f64,
     /// 0이면 성공, 음수이면 에러를 의미합니다.
     pub status_code: i32,
 }
@@ -31,21 +48,136 @@ pub enum InternalError {
     CalibrationFailed,
     #[error("Sensor is still busy.")]
     SensorStillBusy,
+    #[error("CRC checksum mismatch: expected {expected:#04x}, computed {computed:#04x}")]
+    ChecksumMismatch { expected: u8, computed: u8 },
+}
+
+// --- embedded-hal 트레이트에 대해서만 제네릭한, 플랫폼 독립적인 에러 타입 ---
+// sen0177/bme680/scd4x 드라이버와 동일하게, I2C 구현체의 에러 타입 `E`를 그대로 감쌉니다.
+#[derive(Error, Debug)]
+pub enum SensorError<E> {
+    #[error("I2C communication error")]
+    I2c(E),
+    #[error("Sensor could not be calibrated.")]
+    CalibrationFailed,
+    #[error("Sensor is still busy.")]
+    SensorStillBusy,
+    #[error("CRC checksum mismatch: expected {expected:#04x}, computed {computed:#04x}")]
+    ChecksumMismatch { expected: u8, computed: u8 },
+}
+
+// --- 버스 경로/주소/타이밍을 재컴파일 없이 바꿀 수 있게 해주는 설정 빌더 ---
+// bme680의 `SettingsBuilder`, MS8607의 해상도 설정 메소드들과 동일한 패턴으로,
+// 각 setter가 `Self`를 소비하고 되돌려주어 메소드 체이닝이 가능합니다.
+#[derive(Debug, Clone)]
+pub struct SensorConfig {
+    pub bus_path: String,
+    pub address: u8,
+    pub measurement_delay_ms: u32,
+    pub max_poll_attempts: u32,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            bus_path: "/dev/i2c-1".to_string(),
+            address: 0x38,
+            measurement_delay_ms: 80,
+            max_poll_attempts: 10,
+        }
+    }
+}
+
+impl SensorConfig {
+    pub fn with_bus_path(mut self, bus_path: impl Into<String>) -> Self {
+        self.bus_path = bus_path.into();
+        self
+    }
+
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    pub fn with_measurement_delay_ms(mut self, measurement_delay_ms: u32) -> Self {
+        self.measurement_delay_ms = measurement_delay_ms;
+        self
+    }
+
+    pub fn with_max_poll_attempts(mut self, max_poll_attempts: u32) -> Self {
+        self.max_poll_attempts = max_poll_attempts;
+        self
+    }
 }
 
 // --- 이 함수가 파이썬에서 호출할 수 있도록 외부에 공개됩니다 ---
+// 기본 버스/주소로 동작하는 래퍼이며, 실제 로직은 `read_ahtx0_sensor_at`에 있습니다.
 #[no_mangle]
 pub extern "C" fn read_ahtx0_sensor() -> SensorReading {
+    let default_config = SensorConfig::default();
+    let default_bus = CString::new(default_config.bus_path).unwrap();
+    // SAFETY: `default_bus`는 우리가 직접 구성한 유효한 NUL 종료 문자열입니다.
+    unsafe { read_ahtx0_sensor_at(default_bus.as_ptr(), default_config.address) }
+}
+
+/// 버스 경로와 주소를 직접 지정할 수 있는 FFI 진입점.
+///
+/// `status_code`는 실패 원인을 구분할 수 있도록 `InternalError`의 각 variant마다
+/// 다른 음수 값으로 매핑됩니다:
+///   -1: I2C 통신 에러
+///   -2: 캘리브레이션 실패
+///   -3: 센서가 계속 busy 상태
+///   -4: CRC 체크섬 불일치
+///   -5: bus_path가 유효한 UTF-8 C 문자열이 아님 (잘못된 인자)
+///
+/// # Safety
+/// `bus_path`는 null이거나, NUL로 끝나는 유효한 C 문자열을 가리켜야 합니다.
+#[no_mangle]
+pub unsafe extern "C" fn read_ahtx0_sensor_at(
+    bus_path: *const c_char,
+    address: u8,
+) -> SensorReading {
+    let bus_path = if bus_path.is_null() {
+        SensorConfig::default().bus_path
+    } else {
+        // SAFETY: 호출자가 NUL로 끝나는 유효한 C 문자열을 넘겼다고 가정합니다.
+        match unsafe { CStr::from_ptr(bus_path) }.to_str() {
+            Ok(s) => s.to_string(),
+            Err(_) => {
+                eprintln!("[Rust Library Error] bus_path is not valid UTF-8");
+                return SensorReading {
+                    temperature: 1000.0,
+                    humidity: 1000.0,
+                    dew_point: 1000.0,
+                    humidex: 1000.0,
+                    status_code: -5, // 잘못된 인자 (I2C 에러와 구분)
+                };
+            }
+        }
+    };
+
+    let config = SensorConfig::default()
+        .with_bus_path(bus_path)
+        .with_address(address);
+
     // 내부 로직을 호출하고 결과를 C 호환 구조체로 변환합니다.
-    match read_sensor_internal() {
+    match read_sensor_with_config(&config) {
         Ok(reading) => reading,
         Err(e) => {
-            // 에러가 발생하면 콘솔에 에러를 출력하고, status_code로 실패를 알립니다.
+            // 에러가 발생하면 콘솔에 에러를 출력하고, status_code로 실패 원인을 알립니다.
             eprintln!("[Rust Library Error] {}", e);
+            let status_code = match e {
+                InternalError::I2c(_) => -1,
+                InternalError::CalibrationFailed => -2,
+                InternalError::SensorStillBusy => -3,
+                InternalError::ChecksumMismatch { .. } => -4,
+            };
             SensorReading {
                 temperature: 1000.0,
                 humidity: 1000.0,
-                status_code: -1, // 일반적인 에러 코드
+                dew_point: 1000.0,
+                humidex: 1000.0,
+                status_code,
             }
         }
     }
@@ -73,94 +205,319 @@ pub unsafe extern "C" fn free_string(s: *mut c_char) {
 
 
 // --- 이 함수를 main.rs에서 호출할 것입니다 ---
+// 기본 `SensorConfig`를 꽂아 넣는 얇은 생성자입니다. 버스/주소/타이밍을 바꾸고 싶다면
+// `read_sensor_with_config`를 직접 호출하세요.
 pub fn read_sensor_internal() -> Result<SensorReading, InternalError> {
-    const I2C_BUS_PATH: &str = "/dev/i2c-1";
-    const DEVICE_ADDRESS: u8 = 0x38;
+    read_sensor_with_config(&SensorConfig::default())
+}
 
+// Linux 전용 구성(I2cdev + Delay)을 꽂아 넣고 `SensorConfig`로 동작을 조정합니다.
+// 제네릭 드라이버 본체(`Ahtx0<I2C, D>`)는 플랫폼을 전혀 모르며,
+// 여기서만 `SensorError<I2CError>`를 기존 FFI용 `InternalError`로 되접습니다.
+pub fn read_sensor_with_config(config: &SensorConfig) -> Result<SensorReading, InternalError> {
     // --- FIX: 에러 타입 불일치 해결 ---
     // I2cdev::new()는 `linux_embedded_hal::i2cdev::linux::LinuxI2CError`를 반환합니다.
     // 이 에러를 `map_err`를 사용하여 우리가 처리할 수 있는 `InternalError::I2c`로 수동 변환합니다.
-    let i2c = I2cdev::new(I2C_BUS_PATH).map_err(|e| InternalError::I2c(I2CError::from(e)))?;
+    let i2c = I2cdev::new(&config.bus_path).map_err(|e| InternalError::I2c(I2CError::from(e)))?;
     let delay = Delay;
 
-    let mut sensor = Ahtx0::new(i2c, DEVICE_ADDRESS, delay)?;
-    let reading = sensor.read_temperature_humidity()?;
+    let mut sensor = Ahtx0::new(
+        i2c,
+        config.address,
+        delay,
+        config.measurement_delay_ms,
+        config.max_poll_attempts,
+    )
+    .map_err(collapse_sensor_error)?;
+    let reading = sensor
+        .read_temperature_humidity()
+        .map_err(collapse_sensor_error)?;
 
     Ok(SensorReading {
         temperature: reading.temperature,
         humidity: reading.humidity,
+        dew_point: reading.dew_point,
+        humidex: reading.humidex,
         status_code: 0, // 성공
     })
 }
 
-struct Ahtx0 {
-    i2c: I2cdev,
-    delay: Delay,
+// `SensorError<I2CError>`를 기존 `InternalError`로 변환합니다.
+fn collapse_sensor_error(e: SensorError<I2CError>) -> InternalError {
+    match e {
+        SensorError::I2c(inner) => InternalError::I2c(inner),
+        SensorError::CalibrationFailed => InternalError::CalibrationFailed,
+        SensorError::SensorStillBusy => InternalError::SensorStillBusy,
+        SensorError::ChecksumMismatch { expected, computed } => {
+            InternalError::ChecksumMismatch { expected, computed }
+        }
+    }
+}
+
+// AHTx0/AHT20이 측정 응답의 7번째 바이트로 덧붙이는 CRC8을 계산합니다.
+// 다항식 0x31 (x^8+x^5+x^4+1), 초기값 0xFF, 최종 XOR 없음, MSB-first.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = 0xFF;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            if (crc & 0x80) != 0 {
+                crc = (crc << 1) ^ 0x31;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+// `embedded_hal::i2c::I2c` / `embedded_hal::delay::DelayNs`에 대해서만 제네릭한 드라이버 본체.
+// 특정 플랫폼(Linux, STM32, Embassy, 목(mock) I2C 등)에 얽매이지 않으므로
+// 단위 테스트에서도 목 I2C 구현체를 꽂아 넣을 수 있습니다.
+struct Ahtx0<I2C, D> {
+    i2c: I2C,
+    delay: D,
     address: u8,
+    measurement_delay_ms: u32,
+    max_poll_attempts: u32,
 }
 
-impl Ahtx0 {
-    fn new(i2c: I2cdev, address: u8, delay: Delay) -> Result<Self, InternalError> {
-        let mut sensor = Self { i2c, address, delay };
+impl<I2C, D> Ahtx0<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    fn new(
+        i2c: I2C,
+        address: u8,
+        delay: D,
+        measurement_delay_ms: u32,
+        max_poll_attempts: u32,
+    ) -> Result<Self, SensorError<I2C::Error>> {
+        let mut sensor = Self {
+            i2c,
+            address,
+            delay,
+            measurement_delay_ms,
+            max_poll_attempts,
+        };
         sensor.soft_reset()?;
         sensor.wait_for_calibration()?;
         Ok(sensor)
     }
 
-    fn soft_reset(&mut self) -> Result<(), InternalError> {
+    fn soft_reset(&mut self) -> Result<(), SensorError<I2C::Error>> {
         const CMD_SOFT_RESET: u8 = 0xBA;
-        self.i2c.write(self.address, &[CMD_SOFT_RESET])?;
+        self.i2c
+            .write(self.address, &[CMD_SOFT_RESET])
+            .map_err(SensorError::I2c)?;
         self.delay.delay_ms(20);
         Ok(())
     }
 
-    fn status(&mut self) -> Result<u8, InternalError> {
+    fn status(&mut self) -> Result<StatusFlags, SensorError<I2C::Error>> {
         let mut buffer = [0u8; 1];
-        self.i2c.read(self.address, &mut buffer)?;
-        Ok(buffer[0])
+        self.i2c
+            .read(self.address, &mut buffer)
+            .map_err(SensorError::I2c)?;
+        Ok(StatusFlags::from_bits_truncate(buffer[0]))
     }
 
-    fn wait_for_calibration(&mut self) -> Result<(), InternalError> {
-        for _ in 0..10 {
-            const STATUS_CALIBRATED: u8 = 0x08;
-            if (self.status()? & STATUS_CALIBRATED) == STATUS_CALIBRATED {
+    fn wait_for_calibration(&mut self) -> Result<(), SensorError<I2C::Error>> {
+        for _ in 0..self.max_poll_attempts {
+            if self.status()?.contains(StatusFlags::CALIBRATION_ENABLE) {
                 return Ok(());
             }
             self.delay.delay_ms(10);
         }
-        Err(InternalError::CalibrationFailed)
+        Err(SensorError::CalibrationFailed)
     }
 
-    fn read_temperature_humidity(&mut self) -> Result<RawSensorData, InternalError> {
+    fn read_temperature_humidity(&mut self) -> Result<RawSensorData, SensorError<I2C::Error>> {
         const CMD_TRIGGER: [u8; 3] = [0xAC, 0x33, 0x00];
-        const STATUS_BUSY: u8 = 0x80;
 
-        self.i2c.write(self.address, &CMD_TRIGGER)?;
-        self.delay.delay_ms(80);
+        self.i2c
+            .write(self.address, &CMD_TRIGGER)
+            .map_err(SensorError::I2c)?;
+        self.delay.delay_ms(self.measurement_delay_ms);
+
+        for _ in 0..self.max_poll_attempts {
+            if !self.status()?.contains(StatusFlags::BUSY) {
+                let mut buffer = [0u8; 7];
+                self.i2c
+                    .read(self.address, &mut buffer)
+                    .map_err(SensorError::I2c)?;
+
+                let computed = crc8(&buffer[0..6]);
+                let expected = buffer[6];
+                if computed != expected {
+                    return Err(SensorError::ChecksumMismatch { expected, computed });
+                }
 
-        for _ in 0..10 {
-            if (self.status()? & STATUS_BUSY) == 0 {
-                let mut buffer = [0u8; 6];
-                self.i2c.read(self.address, &mut buffer)?;
                 return Ok(RawSensorData::from_raw_bytes(buffer));
             }
             self.delay.delay_ms(10);
         }
-        Err(InternalError::SensorStillBusy)
+        Err(SensorError::SensorStillBusy)
     }
 }
 
+#[derive(Debug)]
 struct RawSensorData {
     temperature: f64,
     humidity: f64,
+    dew_point: f64,
+    humidex: f64,
 }
 
 impl RawSensorData {
-    fn from_raw_bytes(data: [u8; 6]) -> Self {
+    fn from_raw_bytes(data: [u8; 7]) -> Self {
         let raw_humidity = ((data[1] as u32) << 12) | ((data[2] as u32) << 4) | ((data[3] as u32) >> 4);
         let raw_temp = (((data[3] as u32) & 0x0F) << 16) | ((data[4] as u32) << 8) | (data[5] as u32);
         let humidity = (raw_humidity as f64 / 2_f64.powi(20)) * 100.0;
         let temperature = ((raw_temp as f64 / 2_f64.powi(20)) * 200.0) - 50.0;
-        Self { temperature, humidity }
+        let (dew_point, humidex) = Self::derive_comfort_metrics(temperature, humidity);
+        Self {
+            temperature,
+            humidity,
+            dew_point,
+            humidex,
+        }
+    }
+
+    // 원시 온습도 값으로부터 이슬점과 습도지수(Humidex)를 한 번에 계산합니다.
+    // RH가 0 이하로 들어오면 log(0)을 피하기 위해 계산 전에 아주 작은 양수로 clamp합니다.
+    fn derive_comfort_metrics(temperature: f64, humidity: f64) -> (f64, f64) {
+        let rh = humidity.max(0.001);
+
+        // Magnus 공식: γ = ln(RH/100) + (17.62·T)/(243.12+T)
+        let gamma = (rh / 100.0).ln() + (17.62 * temperature) / (243.12 + temperature);
+        let dew_point = 243.12 * gamma / (17.62 - gamma);
+
+        // 캐나다 기상청 Humidex 공식: T + 0.5555 · (수증기압(hPa) − 10)
+        let vapor_pressure_hpa =
+            (rh / 100.0) * 6.105 * ((17.27 * temperature) / (237.7 + temperature)).exp();
+        let humidex = temperature + 0.5555 * (vapor_pressure_hpa - 10.0);
+
+        (dew_point, humidex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::i2c::{ErrorKind, ErrorType, Operation};
+    use std::collections::VecDeque;
+
+    // 목(mock) I2C 구현체. `Ahtx0<I2C, D>`가 embedded-hal 트레이트에만 의존하므로
+    // 실제 하드웨어 없이도 프로토콜 로직(CRC, busy/calibration 폴링)을 테스트할 수 있습니다.
+    #[derive(Debug)]
+    struct MockI2cError;
+
+    impl embedded_hal::i2c::Error for MockI2cError {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    struct MockI2c {
+        // status() 호출(1바이트 read)마다 순서대로 반환할 상태 바이트.
+        statuses: VecDeque<u8>,
+        // 측정 read(7바이트)마다 순서대로 반환할 데이터.
+        measurements: VecDeque<[u8; 7]>,
+    }
+
+    impl ErrorType for MockI2c {
+        type Error = MockI2cError;
+    }
+
+    impl I2c for MockI2c {
+        fn transaction(
+            &mut self,
+            _address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            for operation in operations {
+                if let Operation::Read(buffer) = operation {
+                    if buffer.len() == 1 {
+                        buffer[0] = self.statuses.pop_front().expect("no status byte queued");
+                    } else {
+                        let data = self.measurements.pop_front().expect("no measurement queued");
+                        buffer.copy_from_slice(&data);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    struct MockDelay;
+
+    impl DelayNs for MockDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    fn measurement_buffer(temperature_humidity: [u8; 6]) -> [u8; 7] {
+        let mut buffer = [0u8; 7];
+        buffer[..6].copy_from_slice(&temperature_humidity);
+        buffer[6] = crc8(&temperature_humidity);
+        buffer
+    }
+
+    #[test]
+    fn crc8_matches_known_vector() {
+        // Sensirion이 문서화한 poly 0x31 / init 0xFF 테스트 벡터.
+        assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn read_temperature_humidity_succeeds_with_valid_crc() {
+        let i2c = MockI2c {
+            statuses: VecDeque::from([StatusFlags::CALIBRATION_ENABLE.bits(), 0x00]),
+            measurements: VecDeque::from([measurement_buffer([0x19, 0x8F, 0x5A, 0x0A, 0x3D, 0x12])]),
+        };
+        let mut sensor = Ahtx0::new(i2c, 0x38, MockDelay, 80, 10).expect("calibration should succeed");
+
+        let reading = sensor
+            .read_temperature_humidity()
+            .expect("valid CRC should be accepted");
+
+        assert!(reading.temperature.is_finite());
+        assert!(reading.humidity.is_finite());
+    }
+
+    #[test]
+    fn read_temperature_humidity_rejects_bad_crc() {
+        let i2c = MockI2c {
+            statuses: VecDeque::from([StatusFlags::CALIBRATION_ENABLE.bits(), 0x00]),
+            measurements: VecDeque::from([[0x19, 0x8F, 0x5A, 0x0A, 0x3D, 0x12, 0x00]]),
+        };
+        let mut sensor = Ahtx0::new(i2c, 0x38, MockDelay, 80, 10).expect("calibration should succeed");
+
+        let err = sensor
+            .read_temperature_humidity()
+            .expect_err("corrupted CRC byte should be rejected");
+
+        assert!(matches!(err, SensorError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn read_temperature_humidity_times_out_while_busy() {
+        let max_poll_attempts = 2;
+        let mut statuses = VecDeque::from([StatusFlags::CALIBRATION_ENABLE.bits()]);
+        statuses.extend(std::iter::repeat_n(StatusFlags::BUSY.bits(), max_poll_attempts as usize));
+
+        let i2c = MockI2c {
+            statuses,
+            measurements: VecDeque::new(),
+        };
+        let mut sensor = Ahtx0::new(i2c, 0x38, MockDelay, 80, max_poll_attempts)
+            .expect("calibration should succeed");
+
+        let err = sensor
+            .read_temperature_humidity()
+            .expect_err("sensor stuck busy should time out");
+
+        assert!(matches!(err, SensorError::SensorStillBusy));
     }
 }